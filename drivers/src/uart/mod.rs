@@ -4,10 +4,10 @@ mod buffered;
 mod uart_16550;
 
 pub use buffered::BufferedUart;
-pub use uart_16550::Uart16550Mmio;
+pub use uart_16550::{Parity, Uart16550Mmio, UartConfig};
 
 #[cfg(target_arch = "x86_64")]
-pub use uart_16550::Uart16550Pmio;
+pub use uart_16550::{Uart16550Pmio, COM1, COM2, COM3, COM4};
 
 #[cfg(target_arch = "aarch64")]
 mod uart_pl011;
@@ -7,7 +7,7 @@ use lock::Mutex;
 use crate::io::{Io, Mmio, ReadOnly};
 use crate::scheme::{impl_event_scheme, Scheme, UartScheme};
 use crate::utils::EventListener;
-use crate::DeviceResult;
+use crate::{DeviceError, DeviceResult};
 
 bitflags! {
     /// Interrupt enable flags
@@ -30,6 +30,94 @@ bitflags! {
     }
 }
 
+/// Input clock of a PC-compatible 16550 (1.8432 MHz) divided by 16.
+const BASE_BAUD: u32 = 115_200;
+
+/// Divisor latch access bit of the line control register.
+const LINE_CTRL_DLAB: u8 = 1 << 7;
+
+/// Parity mode of the serial line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+    /// Parity bit always set.
+    Mark,
+    /// Parity bit always cleared.
+    Space,
+}
+
+/// Baud rate and character framing of a 16550 port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UartConfig {
+    /// Must be non-zero.
+    pub baud: u32,
+    /// Bits per character, 5 to 8.
+    pub data_bits: u8,
+    pub parity: Parity,
+    /// 1 or 2. With 5 data bits the chip sends 1.5 stop bits for 2.
+    pub stop_bits: u8,
+}
+
+impl Default for UartConfig {
+    /// 115200 8N1.
+    fn default() -> Self {
+        Self {
+            baud: BASE_BAUD,
+            data_bits: 8,
+            parity: Parity::None,
+            stop_bits: 1,
+        }
+    }
+}
+
+impl UartConfig {
+    /// Divisor latch value whose rate `BASE_BAUD / divisor` is closest to
+    /// `baud`, assuming the PC 1.8432 MHz input clock.
+    ///
+    /// Returns [`DeviceError::InvalidParam`] if `baud` is zero.
+    pub fn divisor(&self) -> DeviceResult<u16> {
+        if self.baud == 0 {
+            return Err(DeviceError::InvalidParam);
+        }
+        let (base, baud) = (BASE_BAUD as u64, self.baud as u64);
+        let lo = base / baud;
+        if lo == 0 {
+            return Ok(1);
+        }
+        if lo >= u16::MAX as u64 {
+            return Ok(u16::MAX);
+        }
+        // `base / lo >= baud >= base / (lo + 1)`; compare both distances
+        // scaled by `lo * (lo + 1)` to stay in integers.
+        let hi = lo + 1;
+        let above = base * hi - baud * lo * hi;
+        let below = baud * lo * hi - base * lo;
+        Ok(if above <= below { lo } else { hi } as u16)
+    }
+
+    /// Line control register value (with DLAB cleared) for this framing.
+    ///
+    /// Returns [`DeviceError::InvalidParam`] if `data_bits` is not in 5..=8
+    /// or `stop_bits` is not 1 or 2.
+    pub fn line_ctrl(&self) -> DeviceResult<u8> {
+        if !(5..=8).contains(&self.data_bits) || !(1..=2).contains(&self.stop_bits) {
+            return Err(DeviceError::InvalidParam);
+        }
+        let word_len = self.data_bits - 5;
+        let stop = if self.stop_bits == 2 { 1 << 2 } else { 0 };
+        let parity = match self.parity {
+            Parity::None => 0,
+            Parity::Odd => 0b001 << 3,
+            Parity::Even => 0b011 << 3,
+            Parity::Mark => 0b101 << 3,
+            Parity::Space => 0b111 << 3,
+        };
+        Ok(word_len | stop | parity)
+    }
+}
+
 #[repr(C)]
 struct Uart16550Inner<T: Io> {
     /// Data register, read to receive, write to send
@@ -68,6 +156,20 @@ where
         self.int_en.write(0x01.into());
     }
 
+    fn configure(&mut self, config: &UartConfig) -> DeviceResult {
+        let [div_lo, div_hi] = config.divisor()?.to_le_bytes();
+        let line_ctrl = config.line_ctrl()?;
+        // With DLAB set, `data` and `int_en` alias the divisor latch, so
+        // save the interrupt mask and put it back once DLAB is cleared.
+        let int_en = self.int_en.read();
+        self.line_ctrl.write(LINE_CTRL_DLAB.into());
+        self.data.write(div_lo.into());
+        self.int_en.write(div_hi.into());
+        self.line_ctrl.write(line_ctrl.into());
+        self.int_en.write(int_en);
+        Ok(())
+    }
+
     fn line_sts(&self) -> LineStsFlags {
         LineStsFlags::from_bits_truncate(
             (self.line_sts.read() & 0xFF.into()).try_into().unwrap_or(0),
@@ -191,6 +293,15 @@ where
             listener: EventListener::new(),
         }
     }
+
+    /// Set the baud rate and framing. Rates the chip cannot produce
+    /// exactly are rounded to the nearest one it can.
+    ///
+    /// The divisor assumes the PC 1.8432 MHz input clock; boards that feed
+    /// the UART a different clock get a proportionally different rate.
+    pub fn configure(&self, config: &UartConfig) -> DeviceResult {
+        self.inner.lock().configure(config)
+    }
 }
 
 impl Uart16550Mmio<u8> {
@@ -216,6 +327,15 @@ mod pmio {
     use super::*;
     use crate::io::Pmio;
 
+    /// I/O port base of the first serial port.
+    pub const COM1: u16 = 0x3F8;
+    /// I/O port base of the second serial port.
+    pub const COM2: u16 = 0x2F8;
+    /// I/O port base of the third serial port.
+    pub const COM3: u16 = 0x3E8;
+    /// I/O port base of the fourth serial port.
+    pub const COM4: u16 = 0x2E8;
+
     /// Pmio driver for UART 16550
     pub struct Uart16550Pmio {
         inner: Mutex<Uart16550Inner<Pmio<u8>>>,
@@ -266,8 +386,79 @@ mod pmio {
                 listener: EventListener::new(),
             }
         }
+
+        /// Set the baud rate and framing. Rates the chip cannot produce
+        /// exactly are rounded to the nearest one it can.
+        pub fn configure(&self, config: &UartConfig) -> DeviceResult {
+            self.inner.lock().configure(config)
+        }
     }
 }
 
 #[cfg(target_arch = "x86_64")]
-pub use pmio::Uart16550Pmio;
+pub use pmio::{Uart16550Pmio, COM1, COM2, COM3, COM4};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_divisor() {
+        let cfg = |baud| UartConfig {
+            baud,
+            ..Default::default()
+        };
+        let bytes = |baud| cfg(baud).divisor().unwrap().to_le_bytes();
+        assert_eq!(bytes(115_200), [0x01, 0x00]);
+        assert_eq!(bytes(9600), [0x0C, 0x00]);
+        assert_eq!(bytes(300), [0x80, 0x01]);
+        assert_eq!(bytes(50), [0x00, 0x09]);
+        // Unsupported rates pick the closest rate the chip can produce.
+        assert_eq!(cfg(100_000).divisor(), Ok(1));
+        assert_eq!(cfg(80_000).divisor(), Ok(2));
+        assert_eq!(cfg(50_000).divisor(), Ok(2));
+        assert_eq!(cfg(921_600).divisor(), Ok(1));
+        assert_eq!(cfg(1).divisor(), Ok(u16::MAX));
+        assert_eq!(cfg(0).divisor(), Err(DeviceError::InvalidParam));
+    }
+
+    #[test]
+    fn test_line_ctrl() {
+        assert_eq!(UartConfig::default().line_ctrl(), Ok(0x03));
+        let cfg = UartConfig {
+            baud: 9600,
+            data_bits: 7,
+            parity: Parity::Even,
+            stop_bits: 1,
+        };
+        assert_eq!(cfg.line_ctrl(), Ok(0x1A));
+        let cfg = UartConfig {
+            baud: 9600,
+            data_bits: 5,
+            parity: Parity::Odd,
+            stop_bits: 2,
+        };
+        assert_eq!(cfg.line_ctrl(), Ok(0x0C));
+        let cfg = UartConfig {
+            parity: Parity::Space,
+            ..Default::default()
+        };
+        assert_eq!(cfg.line_ctrl(), Ok(0x3B));
+        assert_eq!(cfg.line_ctrl().unwrap() & LINE_CTRL_DLAB, 0);
+    }
+
+    #[test]
+    fn test_invalid_framing() {
+        let cfg = |data_bits, stop_bits| UartConfig {
+            data_bits,
+            stop_bits,
+            ..Default::default()
+        };
+        for (data_bits, stop_bits) in [(4, 1), (9, 1), (8, 0), (8, 3), (9, 0)] {
+            assert_eq!(
+                cfg(data_bits, stop_bits).line_ctrl(),
+                Err(DeviceError::InvalidParam)
+            );
+        }
+    }
+}
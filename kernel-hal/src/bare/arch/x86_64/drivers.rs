@@ -5,17 +5,18 @@ use alloc::format;
 
 use zcore_drivers::irq::x86::Apic;
 use zcore_drivers::scheme::{IrqScheme, SchemeUpcast};
-use zcore_drivers::uart::{BufferedUart, Uart16550Pmio};
+use zcore_drivers::uart::{BufferedUart, Uart16550Pmio, UartConfig, COM1, COM2};
 use zcore_drivers::{Device, DeviceResult};
 
 use super::trap;
 use crate::drivers;
 
 pub(super) fn init_early() -> DeviceResult {
-    let uart = Arc::new(Uart16550Pmio::new(0x3F8));
-    drivers::add_device(Device::Uart(BufferedUart::new(uart)));
-    let uart = Arc::new(Uart16550Pmio::new(0x2F8));
-    drivers::add_device(Device::Uart(BufferedUart::new(uart)));
+    for base in [COM1, COM2] {
+        let uart = Arc::new(Uart16550Pmio::new(base));
+        uart.configure(&UartConfig::default())?;
+        drivers::add_device(Device::Uart(BufferedUart::new(uart)));
+    }
     Ok(())
 }
 